@@ -10,7 +10,8 @@
 //
 // ignore-lexer-test FIXME #15883
 
-//! An implementation of SipHash 2-4.
+//! An implementation of SipHash 2-4, with support for other round counts
+//! such as the faster SipHash 1-3.
 //!
 //! See: http://131002.net/siphash/
 //!
@@ -30,7 +31,9 @@ use default::Default;
 
 use super::{Hash, Hasher, Writer};
 
-/// `SipState` computes a SipHash 2-4 hash over a stream of bytes.
+/// `SipState` computes a SipHash c-d hash over a stream of bytes, where `c`
+/// is the number of compression rounds per message block and `d` is the
+/// number of finalization rounds.
 pub struct SipState {
     k0: u64,
     k1: u64,
@@ -41,6 +44,9 @@ pub struct SipState {
     v3: u64,
     tail: u64, // unprocessed bytes le
     ntail: uint,  // how many bytes in tail are valid
+    c: uint, // number of compression rounds per block
+    d: uint, // number of finalization rounds
+    is128: bool, // whether this state is keyed for 128-bit output
 }
 
 impl Copy for SipState {}
@@ -98,6 +104,30 @@ impl SipState {
     /// Creates a `SipState` that is keyed off the provided keys.
     #[inline]
     pub fn new_with_keys(key0: u64, key1: u64) -> SipState {
+        SipState::new_with_keys_and_rounds(key0, key1, 2, 4)
+    }
+
+    /// Creates a `SipState`, keyed off the provided keys, configured to
+    /// produce 128-bit output via `result128()`.
+    #[inline]
+    pub fn new_with_keys_128(key0: u64, key1: u64) -> SipState {
+        SipState::new_with_keys_and_rounds_128(key0, key1, 2, 4, true)
+    }
+
+    /// Creates a `SipState` that is keyed off the provided keys, using `c`
+    /// compression rounds per message block and `d` finalization rounds.
+    #[inline]
+    fn new_with_keys_and_rounds(key0: u64, key1: u64, c: uint, d: uint) -> SipState {
+        SipState::new_with_keys_and_rounds_128(key0, key1, c, d, false)
+    }
+
+    /// Creates a `SipState` that is keyed off the provided keys, using `c`
+    /// compression rounds per message block and `d` finalization rounds.
+    /// When `is128` is true the state is keyed for 128-bit output via
+    /// `result128()`; otherwise it is keyed for 64-bit output via `result()`.
+    #[inline]
+    fn new_with_keys_and_rounds_128(key0: u64, key1: u64, c: uint, d: uint,
+                                     is128: bool) -> SipState {
         let mut state = SipState {
             k0: key0,
             k1: key1,
@@ -108,6 +138,9 @@ impl SipState {
             v3: 0,
             tail: 0,
             ntail: 0,
+            c: c,
+            d: d,
+            is128: is128,
         };
         state.reset();
         state
@@ -121,6 +154,9 @@ impl SipState {
         self.v1 = self.k1 ^ 0x646f72616e646f6d;
         self.v2 = self.k0 ^ 0x6c7967656e657261;
         self.v3 = self.k1 ^ 0x7465646279746573;
+        if self.is128 {
+            self.v1 ^= 0xee;
+        }
         self.ntail = 0;
     }
 
@@ -135,18 +171,62 @@ impl SipState {
         let b: u64 = ((self.length as u64 & 0xff) << 56) | self.tail;
 
         v3 ^= b;
-        compress!(v0, v1, v2, v3);
-        compress!(v0, v1, v2, v3);
+        let mut i = 0u;
+        while i < self.c {
+            compress!(v0, v1, v2, v3);
+            i += 1;
+        }
         v0 ^= b;
 
         v2 ^= 0xff;
-        compress!(v0, v1, v2, v3);
-        compress!(v0, v1, v2, v3);
-        compress!(v0, v1, v2, v3);
-        compress!(v0, v1, v2, v3);
+        let mut i = 0u;
+        while i < self.d {
+            compress!(v0, v1, v2, v3);
+            i += 1;
+        }
 
         v0 ^ v1 ^ v2 ^ v3
     }
+
+    /// Returns the computed 128-bit hash as a `(low, high)` pair of `u64`s.
+    ///
+    /// The `SipState` must have been created for 128-bit output, e.g. via
+    /// `new_with_keys_128`.
+    #[inline]
+    pub fn result128(&self) -> (u64, u64) {
+        let mut v0 = self.v0;
+        let mut v1 = self.v1;
+        let mut v2 = self.v2;
+        let mut v3 = self.v3;
+
+        let b: u64 = ((self.length as u64 & 0xff) << 56) | self.tail;
+
+        v3 ^= b;
+        let mut i = 0u;
+        while i < self.c {
+            compress!(v0, v1, v2, v3);
+            i += 1;
+        }
+        v0 ^= b;
+
+        v2 ^= 0xee;
+        let mut i = 0u;
+        while i < self.d {
+            compress!(v0, v1, v2, v3);
+            i += 1;
+        }
+        let h0 = v0 ^ v1 ^ v2 ^ v3;
+
+        v1 ^= 0xdd;
+        let mut i = 0u;
+        while i < self.d {
+            compress!(v0, v1, v2, v3);
+            i += 1;
+        }
+        let h1 = v0 ^ v1 ^ v2 ^ v3;
+
+        (h0, h1)
+    }
 }
 
 impl Writer for SipState {
@@ -168,8 +248,11 @@ impl Writer for SipState {
             let m = self.tail | u8to64_le!(msg, 0, needed) << 8*self.ntail;
 
             self.v3 ^= m;
-            compress!(self.v0, self.v1, self.v2, self.v3);
-            compress!(self.v0, self.v1, self.v2, self.v3);
+            let mut i = 0u;
+            while i < self.c {
+                compress!(self.v0, self.v1, self.v2, self.v3);
+                i += 1;
+            }
             self.v0 ^= m;
 
             self.ntail = 0;
@@ -185,8 +268,11 @@ impl Writer for SipState {
             let mi = u8to64_le!(msg, i);
 
             self.v3 ^= mi;
-            compress!(self.v0, self.v1, self.v2, self.v3);
-            compress!(self.v0, self.v1, self.v2, self.v3);
+            let mut j = 0u;
+            while j < self.c {
+                compress!(self.v0, self.v1, self.v2, self.v3);
+                j += 1;
+            }
             self.v0 ^= mi;
 
             i += 8;
@@ -211,7 +297,10 @@ impl Default for SipState {
     }
 }
 
-/// `SipHasher` computes the SipHash algorithm from a stream of bytes.
+/// `SipHasher` computes the SipHash 2-4 algorithm from a stream of bytes.
+///
+/// It computes the same hashes as `SipHasher24` and is retained for backwards
+/// compatibility.
 #[deriving(Clone)]
 #[allow(missing_copy_implementations)]
 pub struct SipHasher {
@@ -239,7 +328,7 @@ impl SipHasher {
 impl Hasher<SipState> for SipHasher {
     #[inline]
     fn hash<Sized? T: Hash<SipState>>(&self, value: &T) -> u64 {
-        let mut state = SipState::new_with_keys(self.k0, self.k1);
+        let mut state = SipState::new_with_keys_and_rounds(self.k0, self.k1, 2, 4);
         value.hash(&mut state);
         state.result()
     }
@@ -252,6 +341,97 @@ impl Default for SipHasher {
     }
 }
 
+/// `SipHasher13` computes the SipHash 1-3 algorithm from a stream of bytes.
+///
+/// SipHash 1-3 runs a single compression round per message block and three
+/// finalization rounds, trading some of SipHash 2-4's resistance against
+/// adversarial inputs for speed. It is appropriate for hashtables that are
+/// not exposed to attacker-controlled keys.
+#[deriving(Clone)]
+#[allow(missing_copy_implementations)]
+pub struct SipHasher13 {
+    k0: u64,
+    k1: u64,
+}
+
+impl SipHasher13 {
+    /// Creates a `SipHasher13`.
+    #[inline]
+    pub fn new() -> SipHasher13 {
+        SipHasher13::new_with_keys(0, 0)
+    }
+
+    /// Creates a `SipHasher13` that is keyed off the provided keys.
+    #[inline]
+    pub fn new_with_keys(key0: u64, key1: u64) -> SipHasher13 {
+        SipHasher13 {
+            k0: key0,
+            k1: key1,
+        }
+    }
+}
+
+impl Hasher<SipState> for SipHasher13 {
+    #[inline]
+    fn hash<Sized? T: Hash<SipState>>(&self, value: &T) -> u64 {
+        let mut state = SipState::new_with_keys_and_rounds(self.k0, self.k1, 1, 3);
+        value.hash(&mut state);
+        state.result()
+    }
+}
+
+impl Default for SipHasher13 {
+    #[inline]
+    fn default() -> SipHasher13 {
+        SipHasher13::new()
+    }
+}
+
+/// `SipHasher24` computes the SipHash 2-4 algorithm from a stream of bytes.
+///
+/// This is the default, conservative SipHash parameterization recommended
+/// for general-purpose keyed hashing. See `SipHasher13` for a faster, less
+/// conservative alternative.
+#[deriving(Clone)]
+#[allow(missing_copy_implementations)]
+pub struct SipHasher24 {
+    k0: u64,
+    k1: u64,
+}
+
+impl SipHasher24 {
+    /// Creates a `SipHasher24`.
+    #[inline]
+    pub fn new() -> SipHasher24 {
+        SipHasher24::new_with_keys(0, 0)
+    }
+
+    /// Creates a `SipHasher24` that is keyed off the provided keys.
+    #[inline]
+    pub fn new_with_keys(key0: u64, key1: u64) -> SipHasher24 {
+        SipHasher24 {
+            k0: key0,
+            k1: key1,
+        }
+    }
+}
+
+impl Hasher<SipState> for SipHasher24 {
+    #[inline]
+    fn hash<Sized? T: Hash<SipState>>(&self, value: &T) -> u64 {
+        let mut state = SipState::new_with_keys_and_rounds(self.k0, self.k1, 2, 4);
+        value.hash(&mut state);
+        state.result()
+    }
+}
+
+impl Default for SipHasher24 {
+    #[inline]
+    fn default() -> SipHasher24 {
+        SipHasher24::new()
+    }
+}
+
 /// Hashes a value using the SipHash algorithm.
 #[inline]
 pub fn hash<Sized? T: Hash<SipState>>(value: &T) -> u64 {